@@ -1,19 +1,77 @@
-use std::error::Error;
+use std::ffi::CStr;
 use std::fs::File;
 use std::io::prelude::*;
 use std::mem::size_of;
-use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
 
 use libc;
 use libc::{c_int, c_uint, c_ulonglong};
 
 use crate::NixPath;
 use crate::errno::Errno;
+use crate::fcntl::{AtFlags, OFlag};
 
 // re-export the libc::AT_FDCWD const for convenience so that consumers don't need to bring libc
 // in-scope for the one const.
 pub const AT_FDCWD: i32 = libc::AT_FDCWD;
 
+/// Recover the underlying `Errno` from an `io::Error` raised by a `read`/`write` on the fanotify
+/// fd, so callers can match on it (e.g. `Errno::EAGAIN`) the same way they would for any other
+/// syscall wrapper in this crate.
+fn errno_from_io_error(err: std::io::Error) -> Errno {
+    err.raw_os_error()
+        .map(Errno::from_i32)
+        .unwrap_or(Errno::UnknownErrno)
+}
+
+// Info record types carried after the fixed-size `fanotify_event_metadata` header, as defined by
+// `struct fanotify_event_info_header` in `linux/fanotify.h`. These aren't (yet) exposed by libc,
+// so the values are hard-coded here.
+const FAN_EVENT_INFO_TYPE_FID: u8 = 1;
+const FAN_EVENT_INFO_TYPE_DFID_NAME: u8 = 2;
+const FAN_EVENT_INFO_TYPE_DFID: u8 = 3;
+const FAN_EVENT_INFO_TYPE_PIDFD: u8 = 4;
+
+// Sentinel pidfd values, as defined in `linux/fanotify.h`: `FAN_NOPIDFD` means no pidfd could be
+// attached to this event (e.g. the group wasn't initialized with `FAN_REPORT_PIDFD`, or there is
+// no meaningful originating process), while `FAN_EPIDFD` means attaching one failed, typically
+// because the process has already exited.
+const FAN_NOPIDFD: i32 = libc::FAN_NOFD;
+const FAN_EPIDFD: i32 = -2;
+
+// Bit in `struct fanotify_response.response` indicating that one or more
+// `fanotify_response_info_header`-prefixed records follow the base response. Not yet exposed by
+// libc.
+const FAN_INFO: u32 = 0x20;
+
+// The only currently-defined `fanotify_response_info_header.type`, carrying the audit rule a
+// permission decision should be correlated with in the kernel audit log. Not yet exposed by libc.
+const FAN_RESPONSE_INFO_AUDIT_RULE: u8 = 1;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct fanotify_response_info_header {
+    info_type: u8,
+    pad: u8,
+    len: u16,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct fanotify_event_info_header {
+    info_type: u8,
+    pad: u8,
+    len: u16,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct fanotify_event_info_fid {
+    fsid: [i32; 2],
+    // followed by a `struct file_handle` (handle_bytes: u32, handle_type: i32, f_handle: [u8]),
+    // and, for FAN_EVENT_INFO_TYPE_DFID_NAME, a NUL-terminated file name.
+}
+
 libc_bitflags! {
     pub struct InitFlags: c_uint {
         FAN_CLASS_PRE_CONTENT;
@@ -23,6 +81,11 @@ libc_bitflags! {
         FAN_NONBLOCK;
         FAN_UNLIMITED_QUEUE;
         FAN_UNLIMITED_MARKS;
+        FAN_REPORT_FID;
+        FAN_REPORT_DIR_FID;
+        FAN_REPORT_NAME;
+        FAN_REPORT_PIDFD;
+        FAN_ENABLE_AUDIT;
     }
 }
 
@@ -68,11 +131,38 @@ libc_bitflags! {
     }
 }
 
+/// An info record attached to a [`FanotifyEvent`], present when the originating `Fanotify` group
+/// was initialized with one of the `FAN_REPORT_*` flags.
+#[derive(Debug, Clone)]
+pub enum FanotifyInfoRecord {
+    /// A `FAN_EVENT_INFO_TYPE_FID`, `_DFID`, or `_DFID_NAME` record, identifying the filesystem
+    /// and object the event refers to via an opaque file handle, as used by
+    /// `name_to_handle_at(2)`/`open_by_handle_at(2)`.
+    Fid {
+        /// Filesystem ID of the filesystem containing the object.
+        fsid: [i32; 2],
+        /// `handle_type` from the embedded `struct file_handle`.
+        handle_type: i32,
+        /// Raw `f_handle` bytes from the embedded `struct file_handle`.
+        handle: Vec<u8>,
+        /// The object's file name, present only for `FAN_EVENT_INFO_TYPE_DFID_NAME` records.
+        name: Option<std::ffi::CString>,
+    },
+}
+
 #[derive(Debug)]
 pub struct FanotifyEvent {
     pub mask: MaskFlags,
     pub file: Option<File>,
     pub pid: i32,
+    /// Info records describing the event in more detail, populated when `Fanotify` was
+    /// initialized with `FAN_REPORT_FID`, `FAN_REPORT_DIR_FID`, or `FAN_REPORT_NAME`.
+    pub info: Vec<FanotifyInfoRecord>,
+    /// The pidfd of the process responsible for this event, when `Fanotify` was initialized with
+    /// `FAN_REPORT_PIDFD`. `None` if the group isn't reporting pidfds or the kernel had none to
+    /// attach; `Some(Err(_))` if attaching one failed (typically because the process has already
+    /// exited), addressing it via `pid` would then be racy.
+    pub pidfd: Option<crate::Result<OwnedFd>>,
 }
 
 impl FanotifyEvent {
@@ -81,6 +171,94 @@ impl FanotifyEvent {
     }
 }
 
+/// An owned file handle, as obtained from [`name_to_handle_at`] or from the raw bytes reported by
+/// a [`FanotifyInfoRecord::Fid`]. Can be resolved back to an open `File` with
+/// [`open_by_handle_at`].
+#[derive(Debug, Clone)]
+pub struct FileHandle {
+    handle_type: i32,
+    bytes: Vec<u8>,
+}
+
+impl FileHandle {
+    /// Build a `FileHandle` directly from a `handle_type` and the raw `f_handle` bytes, as
+    /// reported by a [`FanotifyInfoRecord::Fid`].
+    pub fn from_raw(handle_type: i32, bytes: Vec<u8>) -> FileHandle {
+        FileHandle { handle_type, bytes }
+    }
+
+    pub fn handle_type(&self) -> i32 {
+        self.handle_type
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Look up the file handle for `path`, relative to `dirfd`, for later use with
+/// [`open_by_handle_at`]. Also returns the mount ID that can be used to locate a suitable mount
+/// fd. See name_to_handle_at(2).
+pub fn name_to_handle_at<P: ?Sized + NixPath>(
+    dirfd: c_int,
+    path: &P,
+    flags: AtFlags,
+) -> crate::Result<(FileHandle, c_int)> {
+    // name_to_handle_at(2) reports the handle size it actually needs via handle_bytes when the
+    // buffer we pass is too small, so start generously and retry once with the reported size.
+    let mut handle_bytes: c_uint = 128;
+
+    loop {
+        let mut buf = vec![0u8; size_of::<libc::file_handle>() + handle_bytes as usize];
+        let fh = buf.as_mut_ptr() as *mut libc::file_handle;
+        unsafe {
+            (*fh).handle_bytes = handle_bytes;
+        }
+        let mut mount_id: c_int = 0;
+
+        let res = path.with_nix_path(|cstr| unsafe {
+            libc::name_to_handle_at(dirfd, cstr.as_ptr(), fh, &mut mount_id, flags.bits())
+        })?;
+
+        let stored = unsafe { (*fh).handle_bytes };
+        match Errno::result(res) {
+            Err(Errno::EOVERFLOW) if stored > handle_bytes => {
+                handle_bytes = stored;
+                continue;
+            }
+            Err(e) => return Err(e),
+            Ok(_) => (),
+        }
+
+        let f_handle_offset = size_of::<libc::file_handle>();
+        let handle = buf[f_handle_offset..f_handle_offset + stored as usize].to_vec();
+        let handle_type = unsafe { (*fh).handle_type };
+
+        return Ok((FileHandle::from_raw(handle_type, handle), mount_id));
+    }
+}
+
+/// Re-open a [`FileHandle`] obtained from [`name_to_handle_at`] or from a
+/// [`FanotifyInfoRecord::Fid`]. `mount_fd` must refer to an open file on the filesystem the
+/// handle belongs to. Returns `Errno::ESTALE` if the handle refers to a deleted or otherwise
+/// unreachable inode. See open_by_handle_at(2).
+pub fn open_by_handle_at(mount_fd: c_int, handle: &FileHandle, flags: OFlag) -> crate::Result<File> {
+    let mut buf = vec![0u8; size_of::<libc::file_handle>() + handle.bytes.len()];
+    let fh = buf.as_mut_ptr() as *mut libc::file_handle;
+    unsafe {
+        (*fh).handle_bytes = handle.bytes.len() as c_uint;
+        (*fh).handle_type = handle.handle_type;
+        std::ptr::copy_nonoverlapping(
+            handle.bytes.as_ptr(),
+            buf.as_mut_ptr().add(size_of::<libc::file_handle>()),
+            handle.bytes.len(),
+        );
+    }
+
+    let res = unsafe { libc::open_by_handle_at(mount_fd, fh, flags.bits()) };
+    Errno::result(res).map(|fd| unsafe { File::from_raw_fd(fd) })
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(u32)]
 pub enum FanotifyPermissionResponse {
@@ -88,15 +266,51 @@ pub enum FanotifyPermissionResponse {
     FAN_DENY = libc::FAN_DENY,
 }
 
+/// An audit rule to attach to a [`FanotifyResponse`], via `FAN_RESPONSE_INFO_AUDIT_RULE`, so a
+/// permission decision can be correlated with the kernel audit rule that triggered it.
+#[derive(Debug, Clone, Copy)]
+pub struct FanotifyResponseAuditRule {
+    pub rule_number: u32,
+    pub subj_trust: u32,
+    pub obj_trust: u32,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct FanotifyResponse {
     pub fd: RawFd,
     pub response: FanotifyPermissionResponse,
+    /// An audit rule to attach as a `FAN_RESPONSE_INFO_AUDIT_RULE` record. Only accepted by
+    /// [`Fanotify::respond`] when the group was initialized with `FAN_ENABLE_AUDIT`.
+    pub audit_rule: Option<FanotifyResponseAuditRule>,
+}
+
+impl FanotifyResponse {
+    /// Build a `FanotifyResponse` for the common case of a plain permission decision, with no
+    /// `audit_rule` attached. Use the struct literal directly if an audit rule is needed.
+    pub fn new(fd: RawFd, response: FanotifyPermissionResponse) -> FanotifyResponse {
+        FanotifyResponse {
+            fd,
+            response,
+            audit_rule: None,
+        }
+    }
 }
 
+// Comfortably larger than any single event can be: the fixed `fanotify_event_metadata` header,
+// an info record with an embedded file handle, and (for `FAN_REPORT_NAME`) a full NAME_MAX-sized
+// file name.
+const FANOTIFY_EVENT_BUF_LEN: usize = 8192;
+
 #[derive(Debug)]
 pub struct Fanotify {
     fd: File,
+    // Bytes left over from the last read: a trailing incomplete event, plus any complete events
+    // beyond the caller's `limit`. Prepended to the caller's buffer on the next
+    // `read_events`/`read_events_into` call instead of being dropped.
+    pending: Vec<u8>,
+    // The flags this group was initialized with, kept around so `respond` can validate that
+    // `FAN_RESPONSE_INFO_AUDIT_RULE` is only sent to a group that can make use of it.
+    init_flags: InitFlags,
 }
 
 impl Fanotify {
@@ -104,6 +318,8 @@ impl Fanotify {
         let res = Errno::result(unsafe { libc::fanotify_init(flags.bits(), event_flags.bits()) });
         res.map(|fd| Fanotify {
             fd: unsafe { File::from_raw_fd(fd) },
+            pending: Vec::new(),
+            init_flags: flags,
         })
     }
 
@@ -126,52 +342,508 @@ impl Fanotify {
         Errno::result(res).map(|_| ())
     }
 
-    pub fn read_events(&mut self) -> Result<Vec<FanotifyEvent>, Box<dyn Error>> {
+    /// Walk the info records trailing a `fanotify_event_metadata` header, stopping cleanly if a
+    /// record's declared `len` would run past the end of `buf`. Returns the parsed `Fid` records
+    /// plus the raw pidfd from a `FAN_EVENT_INFO_TYPE_PIDFD` record, if present.
+    fn parse_info_records(buf: &[u8]) -> (Vec<FanotifyInfoRecord>, Option<i32>) {
+        let header_size = size_of::<fanotify_event_info_header>();
+        let mut records = Vec::new();
+        let mut raw_pidfd = None;
+        let mut offset = 0;
+
+        while buf.len() - offset >= header_size {
+            let header = unsafe {
+                #[allow(clippy::cast_ptr_alignment)]
+                (buf.as_ptr().add(offset) as *const fanotify_event_info_header).read_unaligned()
+            };
+            let len = header.len as usize;
+            if len < header_size || offset + len > buf.len() {
+                break;
+            }
+
+            if matches!(
+                header.info_type,
+                FAN_EVENT_INFO_TYPE_FID | FAN_EVENT_INFO_TYPE_DFID | FAN_EVENT_INFO_TYPE_DFID_NAME
+            ) {
+                let fid_size = size_of::<fanotify_event_info_fid>();
+                let fh_start = offset + header_size + fid_size;
+                if fh_start + 8 <= offset + len {
+                    let fid = unsafe {
+                        #[allow(clippy::cast_ptr_alignment)]
+                        (buf.as_ptr().add(offset + header_size) as *const fanotify_event_info_fid)
+                            .read_unaligned()
+                    };
+
+                    let handle_bytes = u32::from_ne_bytes(
+                        buf[fh_start..fh_start + 4].try_into().unwrap(),
+                    ) as usize;
+                    let handle_type = i32::from_ne_bytes(
+                        buf[fh_start + 4..fh_start + 8].try_into().unwrap(),
+                    );
+                    let f_handle_start = fh_start + 8;
+                    let f_handle_end = f_handle_start + handle_bytes;
+
+                    if f_handle_end <= offset + len {
+                        let handle = buf[f_handle_start..f_handle_end].to_vec();
+
+                        let name = if header.info_type == FAN_EVENT_INFO_TYPE_DFID_NAME
+                            && f_handle_end < offset + len
+                        {
+                            CStr::from_bytes_until_nul(&buf[f_handle_end..offset + len])
+                                .ok()
+                                .map(|s| s.to_owned())
+                        } else {
+                            None
+                        };
+
+                        records.push(FanotifyInfoRecord::Fid {
+                            fsid: fid.fsid,
+                            handle_type,
+                            handle,
+                            name,
+                        });
+                    }
+                }
+            } else if header.info_type == FAN_EVENT_INFO_TYPE_PIDFD && offset + header_size + 4 <= offset + len {
+                raw_pidfd = Some(i32::from_ne_bytes(
+                    buf[offset + header_size..offset + header_size + 4]
+                        .try_into()
+                        .unwrap(),
+                ));
+            }
+
+            offset += len;
+        }
+
+        (records, raw_pidfd)
+    }
+
+    /// Read the fixed `fanotify_event_metadata` header at `offset`, plus the offset of the byte
+    /// after the event it describes. Returns `None` if `buf` doesn't yet hold a complete event at
+    /// `offset` (a short header, or an `event_len` that runs past the end of `buf`), so the
+    /// caller can leave those bytes buffered until more data arrives.
+    fn event_metadata_at(buf: &[u8], offset: usize) -> Option<(libc::fanotify_event_metadata, usize)> {
         let header_size = size_of::<libc::fanotify_event_metadata>();
-        let mut buffer = [0u8; 4096];
+        if buf.len() - offset < header_size {
+            return None;
+        }
+
+        let event = unsafe {
+            // NOTE: Clippy complains that we are casting to "a more-strictly-aligned pointer".
+            // Since we use this casted ptr only as an input to ptr::read_unaligned(ptr as
+            // *const T) this is fine and a false positive to suppress.
+            //
+            // See https://github.com/rust-lang/rust-clippy/issues/2881
+                #[allow(clippy::cast_ptr_alignment)]
+            (buf.as_ptr().add(offset) as *const libc::fanotify_event_metadata).read_unaligned()
+        };
+
+        // A conforming kernel never reports an event_len shorter than the metadata it just
+        // reported, but guard against it anyway: without this, a malformed/adversarial
+        // event_len == 0 would make event_end == offset, so the caller's parse loop would never
+        // advance and spin forever instead of treating the buffer as holding no complete event.
+        if (event.event_len as usize) < header_size {
+            return None;
+        }
+
+        let event_end = offset + event.event_len as usize;
+        if event_end > buf.len() {
+            return None;
+        }
+
+        Some((event, event_end))
+    }
+
+    /// Count how many complete events `buf` holds, without touching any of the fds they carry.
+    /// Used to decide whether it's worth reading more before returning to the caller.
+    fn count_complete_events(buf: &[u8]) -> usize {
+        let mut offset = 0;
+        let mut count = 0;
+
+        while let Some((_, event_end)) = Self::event_metadata_at(buf, offset) {
+            count += 1;
+            offset = event_end;
+        }
+
+        count
+    }
+
+    /// Parse a single event out of `buf` at `offset`, returning the event and the offset of the
+    /// byte after it, or `None` per [`Self::event_metadata_at`].
+    fn parse_one_event(buf: &[u8], offset: usize) -> Option<(FanotifyEvent, usize)> {
+        let (event, event_end) = Self::event_metadata_at(buf, offset)?;
+
+        // Info records start at `metadata_len`, not `sizeof(fanotify_event_metadata)`, so that a
+        // kernel with a larger metadata struct than this crate knows about still parses
+        // correctly.
+        let info_start = offset + event.metadata_len as usize;
+        let (info, raw_pidfd) = Self::parse_info_records(&buf[info_start..event_end]);
+
+        let pidfd = match raw_pidfd {
+            None | Some(FAN_NOPIDFD) => None,
+            Some(FAN_EPIDFD) => Some(Err(Errno::ESRCH)),
+            Some(fd) => Some(Ok(unsafe { OwnedFd::from_raw_fd(fd) })),
+        };
+
+        let event = FanotifyEvent {
+            file: match event.fd {
+                fd if fd != libc::FAN_NOFD => Some(unsafe { File::from_raw_fd(fd) }),
+                _ => None,
+            },
+            mask: MaskFlags::from_bits_truncate(event.mask),
+            pid: event.pid,
+            info,
+            pidfd,
+        };
+
+        Some((event, event_end))
+    }
+
+    /// Parse as many complete events as `buf` holds, up to `limit`. Returns the events plus the
+    /// offset of the first unconsumed byte (the start of a trailing partial event, if any).
+    fn parse_events(buf: &[u8], limit: usize) -> (Vec<FanotifyEvent>, usize) {
         let mut events = Vec::new();
         let mut offset = 0;
 
-        let nread = self.fd.read(&mut buffer)?;
-
-        while (nread - offset) >= header_size {
-            let event = unsafe {
-                // NOTE: Clippy complains that we are casting to "a more-strictly-aligned pointer".
-                // Since we use this casted ptr only as an input to ptr::read_unaligned(ptr as
-                // *const T) this is fine and a false positive to suppress.
-                //
-                // See https://github.com/rust-lang/rust-clippy/issues/2881
-                    #[allow(clippy::cast_ptr_alignment)]
-                (buffer.as_ptr().add(offset) as *const libc::fanotify_event_metadata)
-                    .read_unaligned()
-            };
+        while events.len() < limit {
+            match Self::parse_one_event(buf, offset) {
+                Some((event, next_offset)) => {
+                    events.push(event);
+                    offset = next_offset;
+                }
+                None => break,
+            }
+        }
+
+        (events, offset)
+    }
+
+    /// Read and parse fanotify events using the caller-supplied scratch buffer `buf`, returning
+    /// at most `limit` events.
+    ///
+    /// For a `FAN_NONBLOCK` group this keeps calling `read` until it would block, reading no more
+    /// than needed to gather `limit` events, so a queue backed up past one buffer's worth of
+    /// events isn't silently truncated. If the queue is drained (a `read` would block) before any
+    /// event was gathered, returns `Err(Errno::EAGAIN)`, the same as the group's underlying fd
+    /// would for a direct `read`. For a blocking group a single `read` is issued, matching the
+    /// blocking semantics callers expect (looping would otherwise hang waiting for an event past
+    /// the ones already delivered). Any bytes not returned as events (a partial trailing event,
+    /// or complete events beyond `limit`) are retained for the next call.
+    pub fn read_events_into(
+        &mut self,
+        buf: &mut [u8],
+        limit: usize,
+    ) -> crate::Result<Vec<FanotifyEvent>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        if self.pending.len() > buf.len() {
+            return Err(Errno::EINVAL);
+        }
+
+        let mut filled = self.pending.len();
+        buf[..filled].copy_from_slice(&self.pending);
+        self.pending.clear();
+
+        let single_read = !self.init_flags.contains(InitFlags::FAN_NONBLOCK);
+        let mut would_block = false;
+
+        while Self::count_complete_events(&buf[..filled]) < limit && filled < buf.len() {
+            match self.fd.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    would_block = true;
+                    break;
+                }
+                Err(e) => {
+                    self.pending.extend_from_slice(&buf[..filled]);
+                    return Err(errno_from_io_error(e));
+                }
+            }
+
+            if single_read {
+                break;
+            }
+        }
 
-            events.push(FanotifyEvent {
-                file: match event.fd {
-                    fd if fd != libc::FAN_NOFD => Some(unsafe { File::from_raw_fd(fd) }),
-                    _ => None,
-                },
-                mask: MaskFlags::from_bits_truncate(event.mask),
-                pid: event.pid,
-            });
+        let (events, consumed) = Self::parse_events(&buf[..filled], limit);
+        self.pending.extend_from_slice(&buf[consumed..filled]);
 
-            offset += event.event_len as usize;
+        // For a nonblocking group, surface EAGAIN rather than an empty Vec when the queue is
+        // genuinely drained, so callers can `match` on it the same way they would for any other
+        // nonblocking read in this crate. If some events were already gathered (from `pending` or
+        // an earlier successful read this call), return those instead of erroring.
+        if would_block && events.is_empty() {
+            return Err(Errno::EAGAIN);
         }
 
         Ok(events)
     }
 
-    pub fn respond(&mut self, response: FanotifyResponse) -> Result<(), Box<dyn Error>> {
+    /// Read and parse as many events as a single internal `FANOTIFY_EVENT_BUF_LEN`-byte buffer
+    /// can hold. For a `FAN_NONBLOCK` group this drains the queue down to that buffer's worth of
+    /// events, as described on [`Self::read_events_into`]. For a blocking group only one `read`
+    /// is issued, so a queue backed up past one buffer isn't drained in a single call — it just
+    /// isn't dropped, since any leftover bytes are retained for the next call.
+    pub fn read_events(&mut self) -> crate::Result<Vec<FanotifyEvent>> {
+        let mut buf = [0u8; FANOTIFY_EVENT_BUF_LEN];
+        self.read_events_into(&mut buf, usize::MAX)
+    }
+
+    pub fn respond(&mut self, response: FanotifyResponse) -> crate::Result<()> {
+        let mut resp_code = response.response as u32;
+
+        // The kernel only accepts a FAN_RESPONSE_INFO_AUDIT_RULE record from a group initialized
+        // with FAN_ENABLE_AUDIT; it rejects one from any other group with EINVAL, regardless of
+        // whether it reports FIDs.
+        let info_bytes = match response.audit_rule {
+            Some(audit_rule) => {
+                if !self.init_flags.contains(InitFlags::FAN_ENABLE_AUDIT) {
+                    return Err(Errno::EINVAL);
+                }
+
+                resp_code |= FAN_INFO;
+                Self::encode_audit_rule_info(audit_rule)
+            }
+            None => Vec::new(),
+        };
+
         // Append the FD in native byte order to response_bytes
         let mut response_bytes = response.fd.to_ne_bytes().to_vec();
         // Append the response in native byte order as well
-        let mut resp_code_bytes = (response.response as u32).to_ne_bytes().to_vec();
-        response_bytes.append(&mut resp_code_bytes);
+        response_bytes.extend_from_slice(&resp_code.to_ne_bytes());
+        // Append any trailing FAN_RESPONSE_INFO_* records
+        response_bytes.extend_from_slice(&info_bytes);
+
         // Write the full response to the fanotify fd
-        Ok(self.fd.write_all(&response_bytes)?)
+        self.fd.write_all(&response_bytes).map_err(errno_from_io_error)
     }
 
-    pub fn as_raw_fd(&self) -> RawFd {
+    /// Serialize a `struct fanotify_response_info_audit_rule`: the common
+    /// `fanotify_response_info_header` followed by the rule number and the subject/object trust
+    /// levels. The kernel's `process_access_response_info` rejects anything but exactly
+    /// `sizeof(struct fanotify_response_info_audit_rule)` (16 bytes) with `EINVAL`.
+    fn encode_audit_rule_info(audit_rule: FanotifyResponseAuditRule) -> Vec<u8> {
+        let header = fanotify_response_info_header {
+            info_type: FAN_RESPONSE_INFO_AUDIT_RULE,
+            pad: 0,
+            len: (size_of::<fanotify_response_info_header>() + 3 * size_of::<u32>()) as u16,
+        };
+
+        let mut bytes = unsafe {
+            std::slice::from_raw_parts(
+                &header as *const _ as *const u8,
+                size_of::<fanotify_response_info_header>(),
+            )
+        }
+        .to_vec();
+        bytes.extend_from_slice(&audit_rule.rule_number.to_ne_bytes());
+        bytes.extend_from_slice(&audit_rule.subj_trust.to_ne_bytes());
+        bytes.extend_from_slice(&audit_rule.obj_trust.to_ne_bytes());
+        bytes
+    }
+}
+
+impl AsFd for Fanotify {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for Fanotify {
+    fn as_raw_fd(&self) -> RawFd {
         self.fd.as_raw_fd()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_u8(buf: &mut Vec<u8>, v: u8) {
+        buf.push(v);
+    }
+
+    fn push_u16(buf: &mut Vec<u8>, v: u16) {
+        buf.extend_from_slice(&v.to_ne_bytes());
+    }
+
+    fn push_u32(buf: &mut Vec<u8>, v: u32) {
+        buf.extend_from_slice(&v.to_ne_bytes());
+    }
+
+    fn push_i32(buf: &mut Vec<u8>, v: i32) {
+        buf.extend_from_slice(&v.to_ne_bytes());
+    }
+
+    fn push_u64(buf: &mut Vec<u8>, v: u64) {
+        buf.extend_from_slice(&v.to_ne_bytes());
+    }
+
+    /// Append a FID/DFID/DFID_NAME info record: the common header, an `fanotify_event_info_fid`,
+    /// an embedded `file_handle`, and (for DFID_NAME) a NUL-terminated name.
+    fn push_fid_record(
+        buf: &mut Vec<u8>,
+        info_type: u8,
+        fsid: [i32; 2],
+        handle_type: i32,
+        handle: &[u8],
+        name: Option<&[u8]>,
+    ) {
+        let mut len = size_of::<fanotify_event_info_header>()
+            + size_of::<fanotify_event_info_fid>()
+            + 8
+            + handle.len();
+        if let Some(name) = name {
+            len += name.len() + 1;
+        }
+
+        push_u8(buf, info_type);
+        push_u8(buf, 0);
+        push_u16(buf, len as u16);
+        push_i32(buf, fsid[0]);
+        push_i32(buf, fsid[1]);
+        push_u32(buf, handle.len() as u32);
+        push_i32(buf, handle_type);
+        buf.extend_from_slice(handle);
+        if let Some(name) = name {
+            buf.extend_from_slice(name);
+            buf.push(0);
+        }
+    }
+
+    fn push_pidfd_record(buf: &mut Vec<u8>, pidfd: i32) {
+        push_u8(buf, FAN_EVENT_INFO_TYPE_PIDFD);
+        push_u8(buf, 0);
+        push_u16(buf, 8);
+        push_i32(buf, pidfd);
+    }
+
+    fn push_metadata(buf: &mut Vec<u8>, metadata_len: u16, fd: i32, pid: i32) {
+        push_u32(buf, 0); // event_len, patched in by the caller once the full event is built
+        push_u8(buf, 0); // vers
+        push_u8(buf, 0); // reserved
+        push_u16(buf, metadata_len);
+        push_u64(buf, 0); // mask
+        push_i32(buf, fd);
+        push_i32(buf, pid);
+    }
+
+    #[test]
+    fn parse_info_records_fid() {
+        let mut buf = Vec::new();
+        push_fid_record(&mut buf, FAN_EVENT_INFO_TYPE_FID, [1, 2], 3, &[9, 9, 9, 9], None);
+
+        let (records, pidfd) = Fanotify::parse_info_records(&buf);
+        assert!(pidfd.is_none());
+        assert_eq!(records.len(), 1);
+        let FanotifyInfoRecord::Fid { fsid, handle_type, handle, name } = &records[0];
+        assert_eq!(*fsid, [1, 2]);
+        assert_eq!(*handle_type, 3);
+        assert_eq!(handle.as_slice(), [9, 9, 9, 9]);
+        assert!(name.is_none());
+    }
+
+    #[test]
+    fn parse_info_records_dfid() {
+        let mut buf = Vec::new();
+        push_fid_record(&mut buf, FAN_EVENT_INFO_TYPE_DFID, [4, 5], 6, &[1, 2, 3], None);
+
+        let (records, _) = Fanotify::parse_info_records(&buf);
+        assert_eq!(records.len(), 1);
+        let FanotifyInfoRecord::Fid { fsid, handle_type, handle, name } = &records[0];
+        assert_eq!(*fsid, [4, 5]);
+        assert_eq!(*handle_type, 6);
+        assert_eq!(handle.as_slice(), [1, 2, 3]);
+        assert!(name.is_none());
+    }
+
+    #[test]
+    fn parse_info_records_dfid_name() {
+        let mut buf = Vec::new();
+        push_fid_record(
+            &mut buf,
+            FAN_EVENT_INFO_TYPE_DFID_NAME,
+            [7, 8],
+            9,
+            &[0xaa, 0xbb],
+            Some(b"some-file.txt"),
+        );
+
+        let (records, _) = Fanotify::parse_info_records(&buf);
+        assert_eq!(records.len(), 1);
+        let FanotifyInfoRecord::Fid { fsid, handle_type, handle, name } = &records[0];
+        assert_eq!(*fsid, [7, 8]);
+        assert_eq!(*handle_type, 9);
+        assert_eq!(handle.as_slice(), [0xaa, 0xbb]);
+        assert_eq!(name.as_deref(), Some(c"some-file.txt"));
+    }
+
+    #[test]
+    fn parse_info_records_truncated_record_breaks_cleanly() {
+        let mut buf = Vec::new();
+        // A header claiming a record longer than the buffer actually holds.
+        push_u8(&mut buf, FAN_EVENT_INFO_TYPE_FID);
+        push_u8(&mut buf, 0);
+        push_u16(&mut buf, 200);
+        buf.extend_from_slice(&[0u8; 8]);
+
+        let (records, pidfd) = Fanotify::parse_info_records(&buf);
+        assert!(records.is_empty());
+        assert!(pidfd.is_none());
+    }
+
+    #[test]
+    fn encode_audit_rule_info_layout() {
+        let bytes = Fanotify::encode_audit_rule_info(FanotifyResponseAuditRule {
+            rule_number: 0x1122_3344,
+            subj_trust: 0x5566_7788,
+            obj_trust: 0x99aa_bbcc,
+        });
+
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(bytes[0], FAN_RESPONSE_INFO_AUDIT_RULE);
+        assert_eq!(bytes[1], 0);
+        assert_eq!(u16::from_ne_bytes(bytes[2..4].try_into().unwrap()), 16);
+        assert_eq!(
+            u32::from_ne_bytes(bytes[4..8].try_into().unwrap()),
+            0x1122_3344
+        );
+        assert_eq!(
+            u32::from_ne_bytes(bytes[8..12].try_into().unwrap()),
+            0x5566_7788
+        );
+        assert_eq!(
+            u32::from_ne_bytes(bytes[12..16].try_into().unwrap()),
+            0x99aa_bbcc
+        );
+    }
+
+    #[test]
+    fn parse_one_event_nopidfd_maps_to_none() {
+        let metadata_len = size_of::<libc::fanotify_event_metadata>() as u16;
+        let mut buf = Vec::new();
+        push_metadata(&mut buf, metadata_len, libc::FAN_NOFD, 1234);
+        push_pidfd_record(&mut buf, FAN_NOPIDFD);
+        let event_len = buf.len() as u32;
+        buf[0..4].copy_from_slice(&event_len.to_ne_bytes());
+
+        let (event, consumed) = Fanotify::parse_one_event(&buf, 0).unwrap();
+        assert_eq!(consumed, buf.len());
+        assert!(event.pidfd.is_none());
+    }
+
+    #[test]
+    fn parse_one_event_epidfd_maps_to_esrch_error() {
+        let metadata_len = size_of::<libc::fanotify_event_metadata>() as u16;
+        let mut buf = Vec::new();
+        push_metadata(&mut buf, metadata_len, libc::FAN_NOFD, 1234);
+        push_pidfd_record(&mut buf, FAN_EPIDFD);
+        let event_len = buf.len() as u32;
+        buf[0..4].copy_from_slice(&event_len.to_ne_bytes());
+
+        let (event, _) = Fanotify::parse_one_event(&buf, 0).unwrap();
+        assert!(matches!(event.pidfd, Some(Err(Errno::ESRCH))));
+    }
+}